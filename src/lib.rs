@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use pest::{Parser, iterators::Pair};
 use pest_derive::Parser;
 use serde::{Deserialize, Serialize};
@@ -8,6 +10,9 @@ use thiserror::Error;
 #[grammar = "./grammar.pest"]
 pub struct FirewallGrammar;
 
+/// first-match policy evaluation over parsed rules.
+pub mod policy;
+
 /// parsed firewall rule: service or address rule.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -31,16 +36,26 @@ pub struct AddressRule {
     pub interface: Option<String>,
     pub from: Option<Address>,
     pub to: Option<Address>,
-    pub port: Option<u16>,
+    pub port: Option<PortSpec>,
     pub proto: Option<Protocol>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// a single port, an inclusive range, or a list of either.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum PortSpec {
+    Single(u16),
+    Range { low: u16, high: u16 },
+    List(Vec<PortSpec>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Action {
     Allow,
     Deny,
-    Reject,
+    /// `with` carries an optional reset/redirect target, e.g. `icmp-host-unreachable`.
+    Reject { with: Option<String> },
     Limit,
 }
 
@@ -56,6 +71,10 @@ pub enum Direction {
 pub enum Protocol {
     Tcp,
     Udp,
+    Icmp,
+    Icmpv6,
+    Ah,
+    Esp,
     Any,
 }
 
@@ -65,7 +84,15 @@ pub enum Address {
     Any,
     Internal,
     External,
-    IpCidr(String),
+    IpCidr(CidrAddr),
+}
+
+/// an IP address plus a prefix length, validated at parse time
+/// (0-32 for IPv4, 0-128 for IPv6).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CidrAddr {
+    pub addr: IpAddr,
+    pub prefix: u8,
 }
 
 #[derive(Debug, Error)]
@@ -122,17 +149,29 @@ fn parse_service_rule(pair: Pair<Rule>) -> ParseResult<ServiceRule> {
         .ok_or_else(|| ParseError::Message("service rule missing identifier".into()))?;
 
     Ok(ServiceRule {
-        action: parse_action(action_pair.as_str())?,
+        action: parse_action(action_pair.as_str(), None)?,
         service: ident_pair.as_str().to_string(),
     })
 }
 
 fn parse_address_rule(pair: Pair<Rule>) -> ParseResult<AddressRule> {
-    let mut inner = pair.into_inner();
+    let mut inner = pair.into_inner().peekable();
     let action_pair = inner
         .next()
         .ok_or_else(|| ParseError::Message("address rule missing action".into()))?;
-    let action = parse_action(action_pair.as_str())?;
+
+    let with_target = if matches!(inner.peek().map(Pair::as_rule), Some(Rule::with_clause)) {
+        let with_pair = inner.next().expect("peeked with_clause pair");
+        let ident_pair = with_pair
+            .into_inner()
+            .next()
+            .ok_or_else(|| ParseError::Message("with clause missing identifier".into()))?;
+        Some(ident_pair.as_str().to_string())
+    } else {
+        None
+    };
+
+    let action = parse_action(action_pair.as_str(), with_target)?;
 
     let mut rule = AddressRule {
         action,
@@ -173,8 +212,8 @@ fn parse_address_rule(pair: Pair<Rule>) -> ParseResult<AddressRule> {
                 let port_pair = sub_pair
                     .into_inner()
                     .next()
-                    .ok_or_else(|| ParseError::Message("port clause missing number".into()))?;
-                rule.port = Some(parse_port(port_pair.as_str())?);
+                    .ok_or_else(|| ParseError::Message("port clause missing value".into()))?;
+                rule.port = Some(parse_port_spec(port_pair)?);
             }
             Rule::proto_clause => {
                 let proto_pair = sub_pair
@@ -191,19 +230,40 @@ fn parse_address_rule(pair: Pair<Rule>) -> ParseResult<AddressRule> {
         }
     }
 
+    if let Some(proto) = rule.proto {
+        if rule.port.is_some() && !protocol_allows_ports(proto) {
+            return Err(ParseError::Message(format!(
+                "proto {proto} does not support a port clause"
+            )));
+        }
+    }
+
     Ok(rule)
 }
 
-fn parse_action(text: &str) -> ParseResult<Action> {
+fn protocol_allows_ports(proto: Protocol) -> bool {
+    !matches!(proto, Protocol::Icmp | Protocol::Icmpv6)
+}
+
+fn parse_action(text: &str, with: Option<String>) -> ParseResult<Action> {
     match text {
-        "allow" => Ok(Action::Allow),
-        "deny" => Ok(Action::Deny),
-        "reject" => Ok(Action::Reject),
-        "limit" => Ok(Action::Limit),
+        "allow" => reject_with_on_non_reject(with).map(|()| Action::Allow),
+        "deny" => reject_with_on_non_reject(with).map(|()| Action::Deny),
+        "reject" => Ok(Action::Reject { with }),
+        "limit" => reject_with_on_non_reject(with).map(|()| Action::Limit),
         other => Err(ParseError::Message(format!("invalid action: {other}"))),
     }
 }
 
+fn reject_with_on_non_reject(with: Option<String>) -> ParseResult<()> {
+    match with {
+        None => Ok(()),
+        Some(target) => Err(ParseError::Message(format!(
+            "\"with {target}\" is only valid for the reject action"
+        ))),
+    }
+}
+
 fn parse_direction(text: &str) -> ParseResult<Direction> {
     match text {
         "in" => Ok(Direction::In),
@@ -216,6 +276,10 @@ fn parse_protocol(text: &str) -> ParseResult<Protocol> {
     match text {
         "tcp" => Ok(Protocol::Tcp),
         "udp" => Ok(Protocol::Udp),
+        "icmp" => Ok(Protocol::Icmp),
+        "icmpv6" => Ok(Protocol::Icmpv6),
+        "ah" => Ok(Protocol::Ah),
+        "esp" => Ok(Protocol::Esp),
         "any" => Ok(Protocol::Any),
         other => Err(ParseError::Message(format!("invalid protocol: {other}"))),
     }
@@ -227,15 +291,309 @@ fn parse_address(pair: Pair<Rule>) -> ParseResult<Address> {
         "any" => Ok(Address::Any),
         "internal" => Ok(Address::Internal),
         "external" => Ok(Address::External),
-        _ => Ok(Address::IpCidr(text.to_string())),
+        _ => Ok(Address::IpCidr(parse_cidr_addr(text)?)),
     }
 }
 
-fn parse_port(text: &str) -> ParseResult<u16> {
+fn parse_cidr_addr(text: &str) -> ParseResult<CidrAddr> {
+    let (addr_text, prefix_text) = match text.split_once('/') {
+        Some((addr_text, prefix_text)) => (addr_text, Some(prefix_text)),
+        None => (text, None),
+    };
+
+    let addr: IpAddr = addr_text
+        .parse()
+        .map_err(|_| ParseError::Message(format!("invalid ip address: {addr_text}")))?;
+
+    let max_prefix: u8 = match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    };
+
+    let prefix = match prefix_text {
+        Some(prefix_text) => prefix_text
+            .parse::<u8>()
+            .map_err(|_| ParseError::Message(format!("invalid cidr prefix: {prefix_text}")))?,
+        None => max_prefix,
+    };
+
+    if prefix > max_prefix {
+        return Err(ParseError::Message(format!(
+            "cidr prefix {prefix} out of range for {addr} (max {max_prefix})"
+        )));
+    }
+
+    Ok(CidrAddr { addr, prefix })
+}
+
+fn parse_port_spec(pair: Pair<Rule>) -> ParseResult<PortSpec> {
+    match pair.as_rule() {
+        Rule::port_number => Ok(PortSpec::Single(parse_port_number(pair.as_str())?)),
+        Rule::port_range => parse_port_range(pair),
+        Rule::port_list => {
+            let mut specs = Vec::new();
+            for item_pair in pair.into_inner() {
+                specs.push(parse_port_spec(item_pair)?);
+            }
+            Ok(PortSpec::List(specs))
+        }
+        other => Err(ParseError::Message(format!(
+            "unexpected rule inside port_clause: {other:?}"
+        ))),
+    }
+}
+
+fn parse_port_range(pair: Pair<Rule>) -> ParseResult<PortSpec> {
+    let mut inner = pair.into_inner();
+    let low_pair = inner
+        .next()
+        .ok_or_else(|| ParseError::Message("port range missing low bound".into()))?;
+    let high_pair = inner
+        .next()
+        .ok_or_else(|| ParseError::Message("port range missing high bound".into()))?;
+
+    let low = parse_port_number(low_pair.as_str())?;
+    let high = parse_port_number(high_pair.as_str())?;
+    if low > high {
+        return Err(ParseError::Message(format!(
+            "invalid port range: {low}-{high} (low must be <= high)"
+        )));
+    }
+
+    Ok(PortSpec::Range { low, high })
+}
+
+fn parse_port_number(text: &str) -> ParseResult<u16> {
     text.parse::<u16>()
         .map_err(|_| ParseError::Message(format!("invalid port value: {text}")))
 }
 
+/// a line that failed to parse during [`parse_rules_lenient`].
+#[derive(Debug)]
+pub struct LineError {
+    pub line_number: usize,
+    pub text: String,
+    pub error: ParseError,
+}
+
+/// parses firewall rules file line-by-line, tolerating malformed lines.
+/// unlike [`parse_rules`], a bad line does not abort the whole call: it is
+/// recorded as a [`LineError`] and every other line is still parsed.
+pub fn parse_rules_lenient(input: &str) -> (Vec<FirewallRule>, Vec<LineError>) {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, raw_line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = raw_line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        match raw_line.parse::<FirewallRule>() {
+            Ok(rule) => rules.push(rule),
+            Err(error) => errors.push(LineError {
+                line_number,
+                text: raw_line.to_string(),
+                error,
+            }),
+        }
+    }
+
+    (rules, errors)
+}
+
+impl std::str::FromStr for FirewallRule {
+    type Err = ParseError;
+
+    /// parses a single line in isolation (a rule plus an optional trailing
+    /// comment), without requiring the surrounding file/NEWLINE grammar.
+    fn from_str(s: &str) -> ParseResult<Self> {
+        let rule_text = strip_comment(s);
+        if let Ok(address_rule) = rule_text.parse::<AddressRule>() {
+            return Ok(FirewallRule::Address(address_rule));
+        }
+        Ok(FirewallRule::Service(rule_text.parse::<ServiceRule>()?))
+    }
+}
+
+impl std::str::FromStr for ServiceRule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> ParseResult<Self> {
+        let rule_text = strip_comment(s);
+        let mut pairs = FirewallGrammar::parse(Rule::service_rule, rule_text)?;
+        let pair = pairs
+            .next()
+            .ok_or_else(|| ParseError::Message("expected service_rule pair to be present".into()))?;
+        ensure_fully_consumed(&pair, rule_text)?;
+        parse_service_rule(pair)
+    }
+}
+
+impl std::str::FromStr for AddressRule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> ParseResult<Self> {
+        let rule_text = strip_comment(s);
+        let mut pairs = FirewallGrammar::parse(Rule::addr_rule, rule_text)?;
+        let pair = pairs
+            .next()
+            .ok_or_else(|| ParseError::Message("expected addr_rule pair to be present".into()))?;
+        ensure_fully_consumed(&pair, rule_text)?;
+        parse_address_rule(pair)
+    }
+}
+
+/// strips a trailing `# comment` (if any) and surrounding whitespace from a
+/// single line, so the rule-level grammar rules (which don't anchor on `EOI`
+/// or skip leading whitespace the way the whole-file `line` rule does) can be
+/// invoked directly on input lifted straight from a rules file.
+fn strip_comment(s: &str) -> &str {
+    s.split('#').next().unwrap_or(s).trim()
+}
+
+/// rejects trailing input a rule-level pest rule silently left unconsumed,
+/// since `Parser::parse` only requires a match at the *start* of the input.
+fn ensure_fully_consumed(pair: &Pair<Rule>, original: &str) -> ParseResult<()> {
+    let trailing = original[pair.as_span().end()..].trim();
+    if trailing.is_empty() {
+        Ok(())
+    } else {
+        Err(ParseError::Message(format!(
+            "unexpected trailing input after rule: {trailing}"
+        )))
+    }
+}
+
+impl std::fmt::Display for FirewallRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirewallRule::Service(service_rule) => write!(f, "{service_rule}"),
+            FirewallRule::Address(address_rule) => write!(f, "{address_rule}"),
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.action, self.service)
+    }
+}
+
+impl std::fmt::Display for AddressRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.action)?;
+        if let Some(direction) = self.direction {
+            write!(f, " {direction}")?;
+        }
+        if let Some(interface) = &self.interface {
+            write!(f, " on {interface}")?;
+        }
+        if let Some(from) = &self.from {
+            write!(f, " from {from}")?;
+        }
+        if let Some(to) = &self.to {
+            write!(f, " to {to}")?;
+        }
+        if let Some(port) = &self.port {
+            write!(f, " port {port}")?;
+        }
+        if let Some(proto) = self.proto {
+            write!(f, " proto {proto}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Allow => write!(f, "allow"),
+            Action::Deny => write!(f, "deny"),
+            Action::Reject { with: None } => write!(f, "reject"),
+            Action::Reject { with: Some(target) } => write!(f, "reject with {target}"),
+            Action::Limit => write!(f, "limit"),
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+        };
+        write!(f, "{text}")
+    }
+}
+
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Icmp => "icmp",
+            Protocol::Icmpv6 => "icmpv6",
+            Protocol::Ah => "ah",
+            Protocol::Esp => "esp",
+            Protocol::Any => "any",
+        };
+        write!(f, "{text}")
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Address::Any => write!(f, "any"),
+            Address::Internal => write!(f, "internal"),
+            Address::External => write!(f, "external"),
+            Address::IpCidr(cidr) => write!(f, "{cidr}"),
+        }
+    }
+}
+
+impl std::fmt::Display for CidrAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let max_prefix: u8 = match self.addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if self.prefix == max_prefix {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix)
+        }
+    }
+}
+
+impl std::fmt::Display for PortSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortSpec::Single(port) => write!(f, "{port}"),
+            PortSpec::Range { low, high } => write!(f, "{low}-{high}"),
+            PortSpec::List(specs) => {
+                for (i, spec) in specs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{spec}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// renders parsed rules back to ufw-style text, one rule per line, each
+/// terminated with a newline so the result can be fed straight back into
+/// `parse_rules` (the grammar requires every line to end in `NEWLINE`).
+/// guarantees `parse_rules(&emit_rules(&parse_rules(input)?)) == parse_rules(input)?`.
+pub fn emit_rules(rules: &[FirewallRule]) -> String {
+    rules.iter().map(|rule| format!("{rule}\n")).collect()
+}
+
 /// grammar rule documentation from grammar.pest.
 pub mod grammar_docs {
     /// matches spaces and tabs (silent rule).
@@ -244,25 +602,35 @@ pub mod grammar_docs {
     pub const NEWLINE: &str = r#"NEWLINE = _{ "\r\n" | "\n" }"#;
     pub const COMMENT: &str = r##"COMMENT = _{ "#" ~ (!NEWLINE ~ ANY)* }"##;
     pub const ACTION: &str = r#"action = { "allow" | "deny" | "reject" | "limit" }"#;
+    /// matches "with" keyword followed by a reject target, e.g. "with icmp-host-unreachable".
+    pub const WITH_CLAUSE: &str = r#"with_clause = { "with" ~ ident }"#;
     /// matches direction: in or out.
     pub const DIRECTION: &str = r#"direction = { "in" | "out" }"#;
     pub const IDENT: &str = r#"ident = @{ (ASCII_ALPHANUMERIC | "_" | "-")+ }"#;
-    /// matches ip address or cidr notation.
-    pub const IP: &str = r#"ip = @{ (ASCII_DIGIT | "." | "/")+ }"#;
+    /// matches ipv4/ipv6 address or cidr notation; semantic validation of
+    /// octets, groups, and prefix length happens in `parse_cidr_addr`.
+    pub const IP: &str = r#"ip = @{ (ASCII_HEX_DIGIT | "." | ":" | "/")+ }"#;
     /// matches address: any, internal, external, or ip.
     pub const ADDR: &str = r#"addr = { "any" | "internal" | "external" | ip }"#;
     /// matches port number as digits.
     pub const PORT_NUMBER: &str = r#"port_number = @{ ASCII_DIGIT+ }"#;
-    pub const PORT_CLAUSE: &str = r#"port_clause = { "port" ~ port_number }"#;
-    /// matches protocol: tcp, udp, or any.
-    pub const PROTO: &str = r#"proto = { "tcp" | "udp" | "any" }"#;
+    /// matches an inclusive port range, e.g. "9000-65535".
+    pub const PORT_RANGE: &str = r#"port_range = { port_number ~ "-" ~ port_number }"#;
+    /// matches a comma-separated list of ports and/or ranges.
+    pub const PORT_LIST: &str = r#"port_list = { (port_range | port_number) ~ ("," ~ (port_range | port_number))+ }"#;
+    /// matches "port" keyword followed by a single port, range, or list.
+    pub const PORT_CLAUSE: &str = r#"port_clause = { "port" ~ (port_list | port_range | port_number) }"#;
+    /// matches protocol: tcp, udp, icmp, icmpv6, ah, esp, or any.
+    /// "icmpv6" is tried before "icmp" since PEG alternation is ordered.
+    pub const PROTO: &str =
+        r#"proto = { "tcp" | "udp" | "icmpv6" | "icmp" | "ah" | "esp" | "any" }"#;
     pub const PROTO_CLAUSE: &str = r#"proto_clause = { "proto" ~ proto }"#;
     pub const INTERFACE_CLAUSE: &str = r#"interface_clause = { "on" ~ ident }"#;
     /// matches "from" keyword followed by address.
     pub const FROM_CLAUSE: &str = r#"from_clause = { "from" ~ addr }"#;
     pub const TO_CLAUSE: &str = r#"to_clause = { "to" ~ addr }"#;
-    /// matches address rule: action, optional direction/interface, one or more clauses.
-    pub const ADDR_RULE: &str = r#"addr_rule = { action ~ direction? ~ interface_clause? ~ (from_clause | to_clause | port_clause | proto_clause)+ }"#;
+    /// matches address rule: action, optional reject-with/direction/interface, one or more clauses.
+    pub const ADDR_RULE: &str = r#"addr_rule = { action ~ with_clause? ~ direction? ~ interface_clause? ~ (from_clause | to_clause | port_clause | proto_clause)+ }"#;
     pub const SERVICE_RULE: &str = r#"service_rule = { action ~ ident }"#;
     pub const LINE: &str = r#"line = _{ (addr_rule | service_rule) ~ COMMENT? | COMMENT }"#;
     pub const FILE: &str = r#"file = { SOI ~ (line? ~ NEWLINE)* ~ EOI }"#;
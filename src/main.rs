@@ -3,7 +3,7 @@ use std::{fs, path::PathBuf};
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use serde_json;
-use ufw_rule_parser::parse_rules;
+use firewall_parser::{emit_rules, parse_rules};
 
 #[derive(Parser)]
 #[command(
@@ -26,6 +26,10 @@ enum Commands {
         #[arg(short, long, value_name = "OUTPUT")]
         output: Option<PathBuf>,
     },
+    Format {
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+    },
     Credits,
 }
 
@@ -34,6 +38,7 @@ fn main() -> Result<()> {
 
     match cli.command {
         Commands::Parse { path, json, output } => parse_file(path, json, output)?,
+        Commands::Format { path } => format_file(path)?,
         Commands::Credits => {
             println!("ufw rule parser built with pest, anyhow, and thiserror.");
         }
@@ -60,3 +65,10 @@ fn parse_file(path: PathBuf, json: bool, output: Option<PathBuf>) -> Result<()>
     }
     Ok(())
 }
+
+fn format_file(path: PathBuf) -> Result<()> {
+    let contents = fs::read_to_string(&path)?;
+    let rules = parse_rules(&contents)?;
+    print!("{}", emit_rules(&rules));
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! first-match policy evaluation over parsed firewall rules.
+
+use std::net::IpAddr;
+
+use crate::{
+    Action, Address, AddressRule, CidrAddr, Direction, FirewallRule, PortSpec, Protocol,
+    ServiceRule,
+};
+
+/// a packet to evaluate against a [`Policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub direction: Direction,
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub port: u16,
+    pub proto: Protocol,
+}
+
+/// outcome of evaluating a [`Packet`] against a [`Policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    Deny,
+    Reject,
+    Limit,
+    NoMatch,
+}
+
+/// a set of parsed rules plus the CIDR ranges that define "internal".
+#[derive(Debug, Clone)]
+pub struct Policy {
+    rules: Vec<FirewallRule>,
+    internal_ranges: Vec<CidrAddr>,
+}
+
+impl Policy {
+    /// builds a policy from parsed rules and the ranges considered internal.
+    /// any address not covered by `internal_ranges` is treated as external.
+    pub fn new(rules: Vec<FirewallRule>, internal_ranges: Vec<CidrAddr>) -> Self {
+        Self {
+            rules,
+            internal_ranges,
+        }
+    }
+
+    /// walks the rules top-to-bottom and returns the action of the first
+    /// rule whose clauses all match `pkt`, or `Decision::NoMatch` if none do.
+    pub fn evaluate(&self, pkt: &Packet) -> Decision {
+        for rule in &self.rules {
+            let matched = match rule {
+                FirewallRule::Service(service_rule) => self.service_rule_matches(service_rule, pkt),
+                FirewallRule::Address(address_rule) => self.address_rule_matches(address_rule, pkt),
+            };
+            if matched {
+                return decision_for(rule_action(rule));
+            }
+        }
+        Decision::NoMatch
+    }
+
+    fn address_rule_matches(&self, rule: &AddressRule, pkt: &Packet) -> bool {
+        if let Some(direction) = rule.direction {
+            if direction != pkt.direction {
+                return false;
+            }
+        }
+        if let Some(from) = &rule.from {
+            if !self.address_matches(from, pkt.src) {
+                return false;
+            }
+        }
+        if let Some(to) = &rule.to {
+            if !self.address_matches(to, pkt.dst) {
+                return false;
+            }
+        }
+        if let Some(port) = &rule.port {
+            if !port_matches(port, pkt.port) {
+                return false;
+            }
+        }
+        if let Some(proto) = rule.proto {
+            if !proto_matches(proto, pkt.proto) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn service_rule_matches(&self, rule: &ServiceRule, pkt: &Packet) -> bool {
+        match service_lookup(&rule.service) {
+            Some((port, proto)) => pkt.port == port && proto_matches(proto, pkt.proto),
+            None => false,
+        }
+    }
+
+    fn address_matches(&self, address: &Address, ip: IpAddr) -> bool {
+        match address {
+            Address::Any => true,
+            Address::Internal => self.internal_ranges.iter().any(|r| cidr_contains(r, ip)),
+            Address::External => !self.internal_ranges.iter().any(|r| cidr_contains(r, ip)),
+            Address::IpCidr(cidr) => cidr_contains(cidr, ip),
+        }
+    }
+}
+
+fn rule_action(rule: &FirewallRule) -> Action {
+    match rule {
+        FirewallRule::Service(service_rule) => service_rule.action.clone(),
+        FirewallRule::Address(address_rule) => address_rule.action.clone(),
+    }
+}
+
+fn decision_for(action: Action) -> Decision {
+    match action {
+        Action::Allow => Decision::Allow,
+        Action::Deny => Decision::Deny,
+        Action::Reject { .. } => Decision::Reject,
+        Action::Limit => Decision::Limit,
+    }
+}
+
+fn port_matches(spec: &PortSpec, port: u16) -> bool {
+    match spec {
+        PortSpec::Single(p) => *p == port,
+        PortSpec::Range { low, high } => (*low..=*high).contains(&port),
+        PortSpec::List(specs) => specs.iter().any(|spec| port_matches(spec, port)),
+    }
+}
+
+fn proto_matches(rule_proto: Protocol, pkt_proto: Protocol) -> bool {
+    matches!(rule_proto, Protocol::Any) || rule_proto == pkt_proto
+}
+
+/// maps a named service (as used in `ServiceRule`) to its conventional port/proto.
+fn service_lookup(service: &str) -> Option<(u16, Protocol)> {
+    match service {
+        "ssh" => Some((22, Protocol::Tcp)),
+        "ftp" => Some((21, Protocol::Tcp)),
+        "smtp" => Some((25, Protocol::Tcp)),
+        "dns" => Some((53, Protocol::Udp)),
+        "http" => Some((80, Protocol::Tcp)),
+        "https" => Some((443, Protocol::Tcp)),
+        "ntp" => Some((123, Protocol::Udp)),
+        _ => None,
+    }
+}
+
+fn cidr_contains(range: &CidrAddr, ip: IpAddr) -> bool {
+    match (range.addr, ip) {
+        (IpAddr::V4(net), IpAddr::V4(addr)) => {
+            let prefix = range.prefix.min(32);
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            (u32::from(net) & mask) == (u32::from(addr) & mask)
+        }
+        (IpAddr::V6(net), IpAddr::V6(addr)) => {
+            let prefix = range.prefix.min(128);
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            (u128::from(net) & mask) == (u128::from(addr) & mask)
+        }
+        _ => false,
+    }
+}
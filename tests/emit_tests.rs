@@ -0,0 +1,33 @@
+use anyhow::Result;
+
+use firewall_parser::{emit_rules, parse_rules};
+
+#[test]
+fn display_reproduces_canonical_ufw_syntax() -> Result<()> {
+    let rules = parse_rules("allow in on eth0 from internal to external port 443 proto tcp\n")?;
+    assert_eq!(
+        emit_rules(&rules),
+        "allow in on eth0 from internal to external port 443 proto tcp\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn round_trips_through_emit_and_parse() -> Result<()> {
+    let input = r#"
+allow ssh
+allow in on eth0 from internal to external port 443 proto tcp
+deny out to 8.8.8.8 port 53 proto udp
+allow in from any to any port 80,443,8080 proto tcp
+allow in from 2001:db8::/32 to ::1 port 9000-65535 proto tcp
+allow in from any to any proto icmp
+reject with icmp-host-unreachable in to any proto tcp
+"#;
+
+    let rules = parse_rules(input)?;
+    let emitted = emit_rules(&rules);
+    let reparsed = parse_rules(&emitted)?;
+
+    assert_eq!(rules, reparsed);
+    Ok(())
+}
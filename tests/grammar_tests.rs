@@ -1,6 +1,6 @@
 use anyhow::{Result, anyhow};
 use pest::Parser;
-use ufw_rule_parser::{FirewallGrammar, Rule};
+use firewall_parser::{FirewallGrammar, Rule};
 
 #[test]
 fn action_parses_valid_values() -> Result<()> {
@@ -64,6 +64,20 @@ fn port_clause_parses_number() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn port_clause_parses_range_and_list() -> Result<()> {
+    let mut range_pairs = FirewallGrammar::parse(Rule::port_clause, "port 9000-65535")?;
+    assert_eq!(
+        range_pairs.next().unwrap().as_str(),
+        "port 9000-65535"
+    );
+
+    let mut list_pairs = FirewallGrammar::parse(Rule::port_clause, "port 80,443,8080")?;
+    assert_eq!(list_pairs.next().unwrap().as_str(), "port 80,443,8080");
+
+    Ok(())
+}
+
 #[test]
 fn port_number_accepts_digits_only() -> Result<()> {
     let mut ok = FirewallGrammar::parse(Rule::port_number, "65535")?;
@@ -71,15 +85,59 @@ fn port_number_accepts_digits_only() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn port_range_parses_low_and_high() -> Result<()> {
+    let mut pairs = FirewallGrammar::parse(Rule::port_range, "9000-65535")?;
+    assert_eq!(pairs.next().unwrap().as_str(), "9000-65535");
+
+    let err = FirewallGrammar::parse(Rule::port_range, "9000");
+    assert!(err.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn port_list_parses_mixed_singles_and_ranges() -> Result<()> {
+    let mut pairs = FirewallGrammar::parse(Rule::port_list, "80,443,9000-9100")?;
+    assert_eq!(pairs.next().unwrap().as_str(), "80,443,9000-9100");
+
+    let err = FirewallGrammar::parse(Rule::port_list, "80");
+    assert!(err.is_err());
+
+    Ok(())
+}
+
 #[test]
 fn proto_clause_parses_values() -> Result<()> {
-    for text in ["proto tcp", "proto udp", "proto any"] {
+    for text in [
+        "proto tcp",
+        "proto udp",
+        "proto icmp",
+        "proto icmpv6",
+        "proto ah",
+        "proto esp",
+        "proto any",
+    ] {
         let mut pairs = FirewallGrammar::parse(Rule::proto_clause, text)?;
         let pair = pairs.next().ok_or_else(|| anyhow!("no pair"))?;
         assert_eq!(pair.as_str(), text);
     }
 
-    let err = FirewallGrammar::parse(Rule::proto_clause, "proto icmp");
+    let err = FirewallGrammar::parse(Rule::proto_clause, "proto gre");
+    assert!(err.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn with_clause_parses_identifier() -> Result<()> {
+    let mut pairs = FirewallGrammar::parse(Rule::with_clause, "with icmp-host-unreachable")?;
+    assert_eq!(
+        pairs.next().unwrap().as_str(),
+        "with icmp-host-unreachable"
+    );
+
+    let err = FirewallGrammar::parse(Rule::with_clause, "with");
     assert!(err.is_err());
 
     Ok(())
@@ -138,6 +196,16 @@ fn ip_parses_ipv4_and_cidr() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn ip_parses_ipv6_and_cidr() -> Result<()> {
+    let mut ipv6 = FirewallGrammar::parse(Rule::ip, "2001:db8::1")?;
+    assert_eq!(ipv6.next().unwrap().as_str(), "2001:db8::1");
+
+    let mut cidr = FirewallGrammar::parse(Rule::ip, "2001:db8::/32")?;
+    assert_eq!(cidr.next().unwrap().as_str(), "2001:db8::/32");
+    Ok(())
+}
+
 #[test]
 fn interface_clause_parses_identifier() -> Result<()> {
     let mut pairs = FirewallGrammar::parse(Rule::interface_clause, "on eth0")?;
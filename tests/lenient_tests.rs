@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use firewall_parser::{
+    parse_rules_lenient, Action, AddressRule, FirewallRule, ServiceRule,
+};
+
+#[test]
+fn collects_errors_for_bad_lines_while_keeping_good_ones() {
+    let input = "allow ssh\n\
+                 allow in to any port bogus proto tcp\n\
+                 # a comment line\n\
+                 deny out to 8.8.8.8 port 53 proto udp\n\
+                 this is not a rule\n";
+
+    let (rules, errors) = parse_rules_lenient(input);
+
+    assert_eq!(rules.len(), 2);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].line_number, 2);
+    assert_eq!(errors[0].text, "allow in to any port bogus proto tcp");
+    assert_eq!(errors[1].line_number, 5);
+    assert_eq!(errors[1].text, "this is not a rule");
+}
+
+#[test]
+fn firewall_rule_from_str_parses_service_and_address_rules() -> Result<()> {
+    let service: FirewallRule = "allow ssh".parse()?;
+    assert_eq!(
+        service,
+        FirewallRule::Service(ServiceRule {
+            action: Action::Allow,
+            service: "ssh".into(),
+        })
+    );
+
+    let address: FirewallRule = "deny in to any port 53 proto udp".parse()?;
+    assert!(matches!(address, FirewallRule::Address(_)));
+
+    assert!("not a valid rule".parse::<FirewallRule>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn service_rule_and_address_rule_from_str_parse_in_isolation() -> Result<()> {
+    let service: ServiceRule = "allow ssh".parse()?;
+    assert_eq!(service.action, Action::Allow);
+    assert_eq!(service.service, "ssh");
+
+    let address: AddressRule = "allow in to external port 443 proto tcp".parse()?;
+    assert_eq!(address.action, Action::Allow);
+
+    assert!("allow ssh".parse::<AddressRule>().is_err());
+    assert!("allow in to external port 443 proto tcp"
+        .parse::<ServiceRule>()
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn service_rule_and_address_rule_from_str_tolerate_leading_whitespace() -> Result<()> {
+    let service: ServiceRule = "   allow ssh".parse()?;
+    assert_eq!(service.action, Action::Allow);
+    assert_eq!(service.service, "ssh");
+
+    let address: AddressRule = "    allow in to any port 443 proto tcp".parse()?;
+    assert_eq!(address.action, Action::Allow);
+
+    Ok(())
+}
+
+#[test]
+fn service_rule_and_address_rule_from_str_tolerate_trailing_comments() -> Result<()> {
+    let service: ServiceRule = "allow ssh  # admin access".parse()?;
+    assert_eq!(service.action, Action::Allow);
+    assert_eq!(service.service, "ssh");
+
+    let address: AddressRule = "allow in to any port 443 proto tcp # https".parse()?;
+    assert_eq!(address.action, Action::Allow);
+
+    Ok(())
+}
+
+#[test]
+fn firewall_rule_from_str_still_rejects_real_trailing_input() {
+    assert!("allow ssh extra-garbage".parse::<FirewallRule>().is_err());
+    assert!("allow in to any port 443 proto tcp extra-garbage"
+        .parse::<AddressRule>()
+        .is_err());
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 
 use firewall_parser::{
-    parse_rules, Action, Address, AddressRule, Direction, FirewallRule, Protocol, ServiceRule,
+    parse_rules, Action, Address, AddressRule, CidrAddr, Direction, FirewallRule, PortSpec,
+    Protocol, ServiceRule,
 };
 
 #[test]
@@ -32,7 +33,7 @@ deny out to 8.8.8.8 port 53 proto udp
                 interface: Some("eth0".into()),
                 from: Some(Address::Internal),
                 to: Some(Address::External),
-                port: Some(443),
+                port: Some(PortSpec::Single(443)),
                 proto: Some(Protocol::Tcp),
             }),
             FirewallRule::Address(AddressRule {
@@ -40,8 +41,11 @@ deny out to 8.8.8.8 port 53 proto udp
                 direction: Some(Direction::Out),
                 interface: None,
                 from: None,
-                to: Some(Address::IpCidr("8.8.8.8".into())),
-                port: Some(53),
+                to: Some(Address::IpCidr(CidrAddr {
+                    addr: "8.8.8.8".parse().unwrap(),
+                    prefix: 32,
+                })),
+                port: Some(PortSpec::Single(53)),
                 proto: Some(Protocol::Udp),
             }),
         ]
@@ -50,3 +54,143 @@ deny out to 8.8.8.8 port 53 proto udp
     Ok(())
 }
 
+#[test]
+fn parses_port_ranges_and_lists() -> Result<()> {
+    let input = "allow in to external port 9000-65535 proto tcp\n\
+                 allow in to external port 80,443,8080 proto tcp\n";
+
+    let rules = parse_rules(input)?;
+
+    assert_eq!(
+        rules,
+        vec![
+            FirewallRule::Address(AddressRule {
+                action: Action::Allow,
+                direction: Some(Direction::In),
+                interface: None,
+                from: None,
+                to: Some(Address::External),
+                port: Some(PortSpec::Range {
+                    low: 9000,
+                    high: 65535
+                }),
+                proto: Some(Protocol::Tcp),
+            }),
+            FirewallRule::Address(AddressRule {
+                action: Action::Allow,
+                direction: Some(Direction::In),
+                interface: None,
+                from: None,
+                to: Some(Address::External),
+                port: Some(PortSpec::List(vec![
+                    PortSpec::Single(80),
+                    PortSpec::Single(443),
+                    PortSpec::Single(8080),
+                ])),
+                proto: Some(Protocol::Tcp),
+            }),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rejects_inverted_port_range() {
+    let input = "allow in to external port 65535-9000 proto tcp\n";
+    assert!(parse_rules(input).is_err());
+}
+
+#[test]
+fn parses_ipv6_addresses_and_cidr() -> Result<()> {
+    let input = "allow in from 2001:db8::/32 to ::1 port 443 proto tcp\n";
+    let rules = parse_rules(input)?;
+
+    assert_eq!(
+        rules,
+        vec![FirewallRule::Address(AddressRule {
+            action: Action::Allow,
+            direction: Some(Direction::In),
+            interface: None,
+            from: Some(Address::IpCidr(CidrAddr {
+                addr: "2001:db8::".parse().unwrap(),
+                prefix: 32,
+            })),
+            to: Some(Address::IpCidr(CidrAddr {
+                addr: "::1".parse().unwrap(),
+                prefix: 128,
+            })),
+            port: Some(PortSpec::Single(443)),
+            proto: Some(Protocol::Tcp),
+        })]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rejects_out_of_range_cidr_prefix() {
+    assert!(parse_rules("allow in to 10.0.0.0/99 port 80 proto tcp\n").is_err());
+    assert!(parse_rules("allow in to 2001:db8::/200 port 80 proto tcp\n").is_err());
+}
+
+#[test]
+fn rejects_malformed_ip_address() {
+    assert!(parse_rules("allow in to 999.999.0.0/24 port 80 proto tcp\n").is_err());
+}
+
+#[test]
+fn parses_icmp_rule_without_port() -> Result<()> {
+    let input = "allow in from any to any proto icmp\n";
+    let rules = parse_rules(input)?;
+
+    assert_eq!(
+        rules,
+        vec![FirewallRule::Address(AddressRule {
+            action: Action::Allow,
+            direction: Some(Direction::In),
+            interface: None,
+            from: Some(Address::Any),
+            to: Some(Address::Any),
+            port: None,
+            proto: Some(Protocol::Icmp),
+        })]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rejects_port_clause_combined_with_icmp() {
+    assert!(parse_rules("allow in to any port 22 proto icmp\n").is_err());
+    assert!(parse_rules("allow in to any port 22 proto icmpv6\n").is_err());
+}
+
+#[test]
+fn parses_reject_with_target() -> Result<()> {
+    let input = "reject with icmp-host-unreachable in to any proto tcp\n";
+    let rules = parse_rules(input)?;
+
+    assert_eq!(
+        rules,
+        vec![FirewallRule::Address(AddressRule {
+            action: Action::Reject {
+                with: Some("icmp-host-unreachable".into())
+            },
+            direction: Some(Direction::In),
+            interface: None,
+            from: None,
+            to: Some(Address::Any),
+            port: None,
+            proto: Some(Protocol::Tcp),
+        })]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rejects_with_clause_on_non_reject_action() {
+    assert!(parse_rules("allow with icmp-host-unreachable in to any proto tcp\n").is_err());
+}
+
@@ -0,0 +1,96 @@
+use std::net::IpAddr;
+
+use anyhow::Result;
+
+use firewall_parser::parse_rules;
+use firewall_parser::policy::{Decision, Packet, Policy};
+use firewall_parser::{CidrAddr, Direction, Protocol};
+
+fn internal_ranges() -> Vec<CidrAddr> {
+    vec![CidrAddr {
+        addr: "10.0.0.0".parse().unwrap(),
+        prefix: 8,
+    }]
+}
+
+#[test]
+fn first_matching_rule_wins() -> Result<()> {
+    let rules = parse_rules(
+        "deny in from any to any port 22 proto tcp\n\
+         allow in from internal to any port 22 proto tcp\n",
+    )?;
+    let policy = Policy::new(rules, internal_ranges());
+
+    let internal_pkt = Packet {
+        direction: Direction::In,
+        src: "10.1.2.3".parse::<IpAddr>()?,
+        dst: "192.0.2.1".parse::<IpAddr>()?,
+        port: 22,
+        proto: Protocol::Tcp,
+    };
+    // the earlier "deny" rule matches first, so the later "allow" never runs.
+    assert_eq!(policy.evaluate(&internal_pkt), Decision::Deny);
+
+    Ok(())
+}
+
+#[test]
+fn unmatched_packet_is_no_match() -> Result<()> {
+    let rules = parse_rules("allow in from internal to any port 22 proto tcp\n")?;
+    let policy = Policy::new(rules, internal_ranges());
+
+    let pkt = Packet {
+        direction: Direction::Out,
+        src: "203.0.113.5".parse::<IpAddr>()?,
+        dst: "203.0.113.6".parse::<IpAddr>()?,
+        port: 80,
+        proto: Protocol::Tcp,
+    };
+    assert_eq!(policy.evaluate(&pkt), Decision::NoMatch);
+
+    Ok(())
+}
+
+#[test]
+fn service_rule_resolves_to_default_port_and_proto() -> Result<()> {
+    let rules = parse_rules("allow ssh\n")?;
+    let policy = Policy::new(rules, internal_ranges());
+
+    let matching = Packet {
+        direction: Direction::In,
+        src: "10.0.0.5".parse::<IpAddr>()?,
+        dst: "10.0.0.6".parse::<IpAddr>()?,
+        port: 22,
+        proto: Protocol::Tcp,
+    };
+    assert_eq!(policy.evaluate(&matching), Decision::Allow);
+
+    let non_matching = Packet {
+        port: 23,
+        ..matching
+    };
+    assert_eq!(policy.evaluate(&non_matching), Decision::NoMatch);
+
+    Ok(())
+}
+
+#[test]
+fn port_range_and_list_match_within_policy() -> Result<()> {
+    let rules = parse_rules("allow in from any to any port 9000-9100 proto tcp\n")?;
+    let policy = Policy::new(rules, internal_ranges());
+
+    let pkt = Packet {
+        direction: Direction::In,
+        src: "198.51.100.1".parse::<IpAddr>()?,
+        dst: "198.51.100.2".parse::<IpAddr>()?,
+        port: 9050,
+        proto: Protocol::Tcp,
+    };
+    assert_eq!(policy.evaluate(&pkt), Decision::Allow);
+
+    let rules = parse_rules("allow in from any to any port 80,443,8080 proto tcp\n")?;
+    let policy = Policy::new(rules, internal_ranges());
+    assert_eq!(policy.evaluate(&Packet { port: 443, ..pkt }), Decision::Allow);
+
+    Ok(())
+}